@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::{Cache, MemoryCache, RedisCache};
+
+/// Checks the in-memory layer first and falls back to Redis, populating the
+/// in-memory layer on a Redis hit so repeat lookups skip the network.
+pub struct HybridCache {
+    memory: MemoryCache,
+    redis: RedisCache,
+}
+
+impl HybridCache {
+    pub fn new(memory: MemoryCache, redis: RedisCache) -> Self {
+        Self { memory, redis }
+    }
+}
+
+#[async_trait]
+impl Cache for HybridCache {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        if let Some(value) = self.memory.get(key).await? {
+            return Ok(Some(value));
+        }
+
+        let Some((value, ttl)) = self.redis.get_with_ttl(key).await? else {
+            return Ok(None);
+        };
+
+        self.memory.set(key, value.clone(), ttl).await?;
+
+        Ok(Some(value))
+    }
+
+    async fn set(&self, key: &str, value: String, ttl: Option<Duration>) -> anyhow::Result<()> {
+        self.memory.set(key, value.clone(), ttl).await?;
+        self.redis.set(key, value, ttl).await
+    }
+
+    async fn get_many(&self, keys: &[String]) -> anyhow::Result<Vec<Option<String>>> {
+        let mut values = vec![None; keys.len()];
+        let mut miss_indices = Vec::new();
+        let mut miss_keys = Vec::new();
+
+        for (i, key) in keys.iter().enumerate() {
+            if let Some(value) = self.memory.get(key).await? {
+                values[i] = Some(value);
+            } else {
+                miss_indices.push(i);
+                miss_keys.push(key.clone());
+            }
+        }
+
+        if !miss_keys.is_empty() {
+            let redis_values = self.redis.get_many_with_ttl(&miss_keys).await?;
+
+            for (i, value) in miss_indices.into_iter().zip(redis_values) {
+                if let Some((value, ttl)) = &value {
+                    self.memory.set(&keys[i], value.clone(), *ttl).await?;
+                }
+                values[i] = value.map(|(value, _)| value);
+            }
+        }
+
+        Ok(values)
+    }
+
+    async fn set_many(
+        &self,
+        entries: Vec<(String, String, Option<Duration>)>,
+    ) -> anyhow::Result<()> {
+        for (key, value, ttl) in &entries {
+            self.memory.set(key, value.clone(), *ttl).await?;
+        }
+
+        self.redis.set_many(entries).await
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use moka::sync::Cache as MokaCache;
+
+use super::Cache;
+
+/// Default bound on the number of entries kept in memory.
+const DEFAULT_MAX_CAPACITY: u64 = 100_000;
+
+/// Bounded in-process cache backed by `moka`. Entries carry their own
+/// expiry so per-key TTLs set by an `RpcCacheHandler` are respected even
+/// though the underlying cache has a single eviction policy.
+pub struct MemoryCache {
+    inner: MokaCache<String, (String, Option<Instant>)>,
+}
+
+impl MemoryCache {
+    pub fn new(max_capacity: u64) -> Self {
+        Self {
+            inner: MokaCache::new(max_capacity),
+        }
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CAPACITY)
+    }
+}
+
+#[async_trait]
+impl Cache for MemoryCache {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let Some((value, expires_at)) = self.inner.get(key) else {
+            return Ok(None);
+        };
+
+        if expires_at.is_some_and(|expires_at| expires_at <= Instant::now()) {
+            self.inner.invalidate(key);
+            return Ok(None);
+        }
+
+        Ok(Some(value))
+    }
+
+    async fn set(&self, key: &str, value: String, ttl: Option<Duration>) -> anyhow::Result<()> {
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        self.inner.insert(key.to_string(), (value, expires_at));
+        Ok(())
+    }
+}
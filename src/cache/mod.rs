@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+#[cfg(feature = "hybrid")]
+mod hybrid;
+mod memory;
+#[cfg(feature = "redis-backend")]
+mod redis;
+
+#[cfg(feature = "hybrid")]
+pub use hybrid::HybridCache;
+pub use memory::MemoryCache;
+#[cfg(feature = "redis-backend")]
+pub use redis::RedisCache;
+
+/// A cache for serialized RPC responses, keyed by the method-specific cache
+/// key produced by an `RpcCacheHandler`.
+#[async_trait]
+pub trait Cache: Send + Sync {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<String>>;
+
+    /// Store `value` under `key`. `ttl` of `None` means the entry never
+    /// expires on its own.
+    async fn set(&self, key: &str, value: String, ttl: Option<Duration>) -> anyhow::Result<()>;
+
+    /// Batched read, one result per input key in the same order. The
+    /// default loops `get` one key at a time; backends that support native
+    /// batching (e.g. Redis `MGET`) should override this.
+    async fn get_many(&self, keys: &[String]) -> anyhow::Result<Vec<Option<String>>> {
+        let mut values = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            values.push(self.get(key).await?);
+        }
+
+        Ok(values)
+    }
+
+    /// Batched write of `(key, value, ttl)` entries. The default loops
+    /// `set` one entry at a time; backends that support native batching
+    /// (e.g. a Redis pipeline) should override this to flush everything in
+    /// a single round trip.
+    async fn set_many(
+        &self,
+        entries: Vec<(String, String, Option<Duration>)>,
+    ) -> anyhow::Result<()> {
+        for (key, value, ttl) in entries {
+            self.set(&key, value, ttl).await?;
+        }
+
+        Ok(())
+    }
+}
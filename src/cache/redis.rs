@@ -0,0 +1,272 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use tokio_retry::strategy::{jitter, ExponentialBackoff};
+use tokio_retry::Retry;
+
+use super::Cache;
+
+/// Number of retries made after a failed first attempt before giving up on
+/// a Redis operation.
+const MAX_RETRIES: usize = 4;
+
+/// Upper bound on a single retry delay. This runs on the hot path of every
+/// cache lookup, so an outage must degrade to a cache miss in well under a
+/// second rather than stalling on an unbounded exponential backoff.
+const MAX_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Converts a Redis `PTTL` reply (milliseconds, or a negative sentinel for
+/// "no expiry"/"no such key") into the `Option<Duration>` the rest of the
+/// cache layer works with.
+fn ttl_from_pttl(pttl: i64) -> Option<Duration> {
+    if pttl > 0 {
+        Some(Duration::from_millis(pttl as u64))
+    } else {
+        None
+    }
+}
+
+/// Rounds a TTL down to whole seconds for `SET EX`, clamped to at least 1
+/// second: Redis rejects `EX 0` as an invalid expiry, which a sub-second
+/// handler TTL would otherwise round down to.
+fn ttl_secs(ttl: Duration) -> u64 {
+    ttl.as_secs().max(1)
+}
+
+/// Cache backed by a Redis server, shared across proxy instances.
+///
+/// Holds a single multiplexed async connection (cheap to clone, safe to
+/// share across requests) instead of opening a fresh blocking connection
+/// per call, and retries transient failures with jittered exponential
+/// backoff before giving up.
+pub struct RedisCache {
+    conn: redis::aio::MultiplexedConnection,
+}
+
+impl RedisCache {
+    pub async fn new(redis_url: &str) -> anyhow::Result<Self> {
+        let client = redis::Client::open(redis_url)?;
+        let conn = client.get_multiplexed_async_connection().await?;
+        Ok(Self { conn })
+    }
+
+    fn retry_strategy() -> impl Iterator<Item = Duration> {
+        ExponentialBackoff::from_millis(10)
+            .max_delay(MAX_RETRY_DELAY)
+            .map(jitter)
+            .take(MAX_RETRIES)
+    }
+
+    /// Like `get`, but also returns the key's remaining TTL (via `PTTL`) so
+    /// callers repopulating a faster cache layer don't have to treat the
+    /// value as permanent.
+    pub async fn get_with_ttl(
+        &self,
+        key: &str,
+    ) -> anyhow::Result<Option<(String, Option<Duration>)>> {
+        let conn = self.conn.clone();
+        let key = key.to_string();
+
+        let result = Retry::start(Self::retry_strategy(), || {
+            let mut conn = conn.clone();
+            let key = key.clone();
+            async move {
+                redis::pipe()
+                    .get(&key)
+                    .pttl(&key)
+                    .query_async::<_, (Option<String>, i64)>(&mut conn)
+                    .await
+            }
+        })
+        .await;
+
+        match result {
+            Ok((Some(value), pttl)) => Ok(Some((value, ttl_from_pttl(pttl)))),
+            Ok((None, _)) => Ok(None),
+            Err(err) => {
+                log::error!(
+                    "redis get for key {} failed after retries, treating as a cache miss: {}",
+                    key,
+                    err
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    /// Batched form of `get_with_ttl`. Issues one `MGET` for the values
+    /// followed by one pipeline of `PTTL`s, rather than a `GET`+`PTTL` pair
+    /// per key, since redis-rs flattens a pipeline of non-uniform replies
+    /// into one reply per command rather than one per key.
+    pub async fn get_many_with_ttl(
+        &self,
+        keys: &[String],
+    ) -> anyhow::Result<Vec<Option<(String, Option<Duration>)>>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let values = self.get_many(keys).await?;
+
+        let conn = self.conn.clone();
+        let keys_vec = keys.to_vec();
+
+        let pttls = Retry::start(Self::retry_strategy(), || {
+            let mut conn = conn.clone();
+            let keys_vec = keys_vec.clone();
+            async move {
+                let mut pipe = redis::pipe();
+                for key in &keys_vec {
+                    pipe.pttl(key);
+                }
+                pipe.query_async::<_, Vec<i64>>(&mut conn).await
+            }
+        })
+        .await;
+
+        let pttls = match pttls {
+            Ok(pttls) => pttls,
+            Err(err) => {
+                log::error!(
+                    "redis pttl batch failed after retries, treating {} values as non-expiring: {}",
+                    keys.len(),
+                    err
+                );
+                vec![-1; keys.len()]
+            }
+        };
+
+        Ok(values
+            .into_iter()
+            .zip(pttls)
+            .map(|(value, pttl)| value.map(|value| (value, ttl_from_pttl(pttl))))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Cache for RedisCache {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let conn = self.conn.clone();
+        let key = key.to_string();
+
+        let result = Retry::start(Self::retry_strategy(), || {
+            let mut conn = conn.clone();
+            let key = key.clone();
+            async move { conn.get::<_, Option<String>>(&key).await }
+        })
+        .await;
+
+        match result {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                log::error!(
+                    "redis get for key {} failed after retries, treating as a cache miss: {}",
+                    key,
+                    err
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    async fn set(&self, key: &str, value: String, ttl: Option<Duration>) -> anyhow::Result<()> {
+        let conn = self.conn.clone();
+        let key = key.to_string();
+
+        let result = Retry::start(Self::retry_strategy(), || {
+            let mut conn = conn.clone();
+            let key = key.clone();
+            let value = value.clone();
+            async move {
+                match ttl {
+                    Some(ttl) => conn.set_ex::<_, _, ()>(&key, value, ttl_secs(ttl)).await,
+                    None => conn.set::<_, _, ()>(&key, value).await,
+                }
+            }
+        })
+        .await;
+
+        if let Err(err) = result {
+            log::error!(
+                "redis set for key {} failed after retries, leaving it uncached: {}",
+                key,
+                err
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn get_many(&self, keys: &[String]) -> anyhow::Result<Vec<Option<String>>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.conn.clone();
+        let keys_vec = keys.to_vec();
+
+        let result = Retry::start(Self::retry_strategy(), || {
+            let mut conn = conn.clone();
+            let keys_vec = keys_vec.clone();
+            async move { conn.mget::<_, Vec<Option<String>>>(keys_vec).await }
+        })
+        .await;
+
+        match result {
+            Ok(values) => Ok(values),
+            Err(err) => {
+                log::error!(
+                    "redis mget failed after retries, treating all {} keys as cache misses: {}",
+                    keys.len(),
+                    err
+                );
+                Ok(vec![None; keys.len()])
+            }
+        }
+    }
+
+    async fn set_many(
+        &self,
+        entries: Vec<(String, String, Option<Duration>)>,
+    ) -> anyhow::Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let conn = self.conn.clone();
+
+        let result = Retry::start(Self::retry_strategy(), || {
+            let mut conn = conn.clone();
+            let entries = entries.clone();
+            async move {
+                let mut pipe = redis::pipe();
+
+                for (key, value, ttl) in &entries {
+                    match ttl {
+                        Some(ttl) => {
+                            pipe.set_ex(key, value, ttl_secs(*ttl));
+                        }
+                        None => {
+                            pipe.set(key, value);
+                        }
+                    }
+                }
+
+                pipe.query_async::<_, ()>(&mut conn).await
+            }
+        })
+        .await;
+
+        if let Err(err) = result {
+            log::error!(
+                "redis pipelined set of {} keys failed after retries, some values may be uncached: {}",
+                entries.len(),
+                err
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -1,40 +1,92 @@
 use std::collections::HashMap;
+#[cfg(feature = "redis-backend")]
+use std::env;
+use std::time::Duration;
 
 use actix_web::{error, web, App, Error, HttpResponse, HttpServer};
 use anyhow::Context;
 use clap::Parser;
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use env_logger::Env;
-use redis::Commands;
 use reqwest::Url;
 use serde_json::{json, Value};
-use std::env;
-
-use crate::cli::Cli;
+use tokio::sync::broadcast;
+
+use crate::cache::{Cache, MemoryCache};
+#[cfg(feature = "hybrid")]
+use crate::cache::HybridCache;
+#[cfg(feature = "redis-backend")]
+use crate::cache::RedisCache;
+use crate::cli::{CacheBackend, Cli};
 use crate::rpc_cache_handler::RpcCacheHandler;
-use lazy_static::lazy_static;
 
+mod cache;
 mod cli;
 mod rpc_cache_handler;
 
-lazy_static! {
-    static ref REDIS: redis::Client = {
-        let redis_host = env::var("REDIS_HOST").unwrap_or_else(|_| "localhost".to_string());
-        let redis_url = format!("redis://{}", redis_host);
-        redis::Client::open(redis_url).expect("Failed to create Redis client")
+/// Builds the Redis connection URL from `--redis-url`, or else assembles
+/// one from `REDIS_HOST` and the `--redis-tls`/`--redis-username`/
+/// `--redis-password`/`--redis-db` flags so TLS-terminated, ACL-protected
+/// managed Redis instances can be reached without a hand-rolled URL.
+#[cfg(feature = "redis-backend")]
+fn redis_url(arg: &Cli) -> String {
+    if let Some(redis_url) = &arg.redis_url {
+        return redis_url.clone();
+    }
+
+    let scheme = if arg.redis_tls { "rediss" } else { "redis" };
+    let host = env::var("REDIS_HOST").unwrap_or_else(|_| "localhost".to_string());
+    let auth = match (&arg.redis_username, &arg.redis_password) {
+        (Some(username), Some(password)) => format!("{}:{}@", username, password),
+        (Some(username), None) => format!("{}@", username),
+        (None, Some(password)) => format!(":{}@", password),
+        (None, None) => String::new(),
     };
+    let db = arg
+        .redis_db
+        .map(|db| format!("/{}", db))
+        .unwrap_or_default();
+
+    format!("{}://{}{}{}", scheme, auth, host, db)
 }
 
+async fn build_cache(arg: &Cli) -> anyhow::Result<Box<dyn Cache>> {
+    Ok(match arg.cache_backend {
+        CacheBackend::Memory => Box::new(MemoryCache::default()),
+        #[cfg(feature = "redis-backend")]
+        CacheBackend::Redis => Box::new(RedisCache::new(&redis_url(arg)).await?),
+        #[cfg(feature = "hybrid")]
+        CacheBackend::Hybrid => {
+            let redis = RedisCache::new(&redis_url(arg)).await?;
+            Box::new(HybridCache::new(MemoryCache::default(), redis))
+        }
+    })
+}
+
+/// Result of an in-flight upstream RPC call, shared with every request that
+/// coalesced onto it.
+type SharedRpcResult = Result<Value, String>;
+
 struct ChainState {
     rpc_url: Url,
     cache_entries: HashMap<String, CacheEntry>,
+    cache: Box<dyn Cache>,
+    default_ttl: Option<Duration>,
+    /// Tracks upstream RPC calls currently in flight, keyed by cache key.
+    /// Concurrent requests for the same key subscribe here instead of
+    /// firing their own upstream call, then are dropped once it resolves.
+    in_flight: DashMap<String, broadcast::Sender<SharedRpcResult>>,
 }
 
 impl ChainState {
-    fn new(rpc_url: Url) -> Self {
+    fn new(rpc_url: Url, cache: Box<dyn Cache>, default_ttl: Option<Duration>) -> Self {
         Self {
             rpc_url,
             cache_entries: Default::default(),
+            cache,
+            default_ttl,
+            in_flight: DashMap::new(),
         }
     }
 }
@@ -56,12 +108,6 @@ struct AppState {
     chains: HashMap<String, ChainState>,
 }
 
-enum CacheStatus {
-    NotAvailable,
-    Cached(String, Value),
-    Missed(String),
-}
-
 async fn request_rpc(rpc_url: Url, body: &Value) -> anyhow::Result<Value> {
     let client = reqwest::Client::new();
 
@@ -76,25 +122,33 @@ async fn request_rpc(rpc_url: Url, body: &Value) -> anyhow::Result<Value> {
     Ok(result)
 }
 
-fn read_cache(handler: &dyn RpcCacheHandler, params: &Value) -> anyhow::Result<CacheStatus> {
-    let cache_key = handler
-        .extract_cache_key(params)
-        .context("fail to extract cache key")?;
-
-    let cache_key = match cache_key {
-        Some(cache_key) => cache_key,
-        None => return Ok(CacheStatus::NotAvailable),
-    };
-
-    let value: Option<String> = REDIS.get_connection().unwrap().get(&cache_key).unwrap();
+/// A single sub-request of a (possibly batched) JSON-RPC call, along with
+/// the cache key it would be served from, if any.
+struct RequestPlan {
+    id: u64,
+    method: String,
+    params: Value,
+    cache_key: Option<String>,
+}
 
-    Ok(if let Some(value) = value {
-        let cache_value =
-            serde_json::from_str::<Value>(&value).context("fail to deserialize cache value")?;
-        CacheStatus::Cached(cache_key, cache_value)
-    } else {
-        CacheStatus::Missed(cache_key)
-    })
+/// Fails every leader request among `leader_ids` that's still waiting in
+/// `chain_state.in_flight`, unblocking any follower that coalesced onto it
+/// with `message` instead of leaving it to wait forever. Safe to call more
+/// than once for the same batch: an already-resolved leader has no entry
+/// left to remove.
+fn fail_pending_leaders(
+    chain_state: &ChainState,
+    leader_ids: &[u64],
+    uncached_requests: &HashMap<u64, (String, Value, Option<String>)>,
+    message: &str,
+) {
+    for id in leader_ids {
+        if let Some((_, _, Some(cache_key))) = uncached_requests.get(id) {
+            if let Some((_, sender)) = chain_state.in_flight.remove(cache_key) {
+                let _ = sender.send(Err(message.to_string()));
+            }
+        }
+    }
 }
 
 #[actix_web::post("/{chain}")]
@@ -115,9 +169,8 @@ async fn rpc_call(
         vec![body.0]
     };
 
-    let mut request_result = HashMap::new();
-    let mut uncached_requests = HashMap::new();
-    let mut ordered_id = vec![];
+    let mut plans = Vec::with_capacity(requests.len());
+    let mut ordered_id = Vec::with_capacity(requests.len());
 
     for request in &requests {
         let id = request["id"]
@@ -126,109 +179,238 @@ async fn rpc_call(
         let method = request["method"]
             .as_str()
             .ok_or_else(|| error::ErrorBadRequest("method not found"))?;
-        let params = &request["params"];
+        let params = request["params"].clone();
 
         ordered_id.push(id);
 
-        let cache_entry = match chain_state.cache_entries.get(method) {
-            Some(cache_entry) => cache_entry,
-            None => {
-                uncached_requests.insert(id, (method.to_string(), params.clone(), None));
-                continue;
-            }
+        let cache_key = match chain_state.cache_entries.get(method) {
+            Some(cache_entry) => cache_entry.handler.extract_cache_key(&params).unwrap_or_else(|err| {
+                log::error!("fail to extract cache key because: {}", err);
+                None
+            }),
+            None => None,
         };
 
-        let result = read_cache(cache_entry.handler.as_ref(), params);
+        plans.push(RequestPlan {
+            id,
+            method: method.to_string(),
+            params,
+            cache_key,
+        });
+    }
 
-        match result {
-            Err(err) => {
-                log::error!("fail to read cache because: {}", err);
-                uncached_requests.insert(id, (method.to_string(), params.clone(), None));
-            }
-            Ok(CacheStatus::NotAvailable) => {
-                log::info!("cache not available for method {}", method);
-                uncached_requests.insert(id, (method.to_string(), params.clone(), None));
-            }
-            Ok(CacheStatus::Cached(cache_key, value)) => {
-                log::info!("cache hit for method {} with key {}", method, cache_key);
-                request_result.insert(id, value);
-            }
-            Ok(CacheStatus::Missed(cache_key)) => {
-                log::info!("cache missed for method {} with key {}", method, cache_key);
-                uncached_requests.insert(id, (method.to_string(), params.clone(), Some(cache_key)));
+    // One round trip for every cacheable key in the batch instead of one
+    // per sub-request.
+    let keys_to_fetch = plans
+        .iter()
+        .filter_map(|plan| plan.cache_key.clone())
+        .collect::<Vec<String>>();
+
+    let cached_values = if keys_to_fetch.is_empty() {
+        Vec::new()
+    } else {
+        chain_state.cache.get_many(&keys_to_fetch).await.unwrap_or_else(|err| {
+            log::error!("fail to batch read cache because: {}", err);
+            vec![None; keys_to_fetch.len()]
+        })
+    };
+
+    let mut request_result = HashMap::new();
+    let mut uncached_requests = HashMap::new();
+    let mut cached_values = cached_values.into_iter();
+
+    for plan in plans {
+        let Some(cache_key) = plan.cache_key else {
+            uncached_requests.insert(plan.id, (plan.method, plan.params, None));
+            continue;
+        };
+
+        match cached_values.next().flatten() {
+            Some(raw_value) => match serde_json::from_str::<Value>(&raw_value) {
+                Ok(value) => {
+                    log::info!("cache hit for method {} with key {}", plan.method, cache_key);
+                    request_result.insert(plan.id, value);
+                }
+                Err(err) => {
+                    log::error!("fail to deserialize cache value because: {}", err);
+                    uncached_requests.insert(plan.id, (plan.method, plan.params, Some(cache_key)));
+                }
+            },
+            None => {
+                log::info!("cache missed for method {} with key {}", plan.method, cache_key);
+                uncached_requests.insert(plan.id, (plan.method, plan.params, Some(cache_key)));
             }
         }
     }
 
     if !uncached_requests.is_empty() {
-        let request_body = Value::Array(
-            uncached_requests
-                .iter()
-                .map(|(id, (method, params, _))| {
-                    json!({
-                        "jsonrpc": "2.0",
-                        "id": id.clone(),
-                        "method": method.to_string(),
-                        "params": params.clone(),
-                    })
-                })
-                .collect::<Vec<Value>>(),
-        );
+        // Requests that share a cache key coalesce onto a single upstream
+        // call: whichever one claims the in-flight slot becomes the
+        // "leader" and fires the request, the rest become "followers" that
+        // await its result instead of hammering the upstream RPC too.
+        let mut leader_ids = Vec::new();
+        let mut followers = Vec::new();
+
+        for (id, (_, _, cache_key)) in &uncached_requests {
+            let Some(cache_key) = cache_key else {
+                leader_ids.push(*id);
+                continue;
+            };
 
-        let rpc_result = request_rpc(chain_state.rpc_url.clone(), &request_body)
-            .await
-            .map_err(|err| {
-                log::error!("fail to make rpc request because: {}", err);
-                error::ErrorInternalServerError(format!(
-                    "fail to make rpc request because: {}",
-                    err
-                ))
-            })?;
-
-        let rpc_result = rpc_result.as_array().ok_or_else(|| {
-            log::error!("invalid rpc response: {}", rpc_result.to_string());
-            error::ErrorInternalServerError("invalid rpc response")
-        })?;
-
-        for response in rpc_result {
-            let id = response["id"]
-                .as_u64()
-                .ok_or_else(|| error::ErrorBadRequest("id not found"))?;
-            let (method, params, cache_key) = uncached_requests.get(&id).unwrap();
-
-            let error = &response["error"];
-            if !error.is_null() {
-                log::error!(
-                    "rpc error: {}, request: {}({}), response: {}",
-                    error.to_string(),
-                    method,
-                    params.to_string(),
-                    response.to_string()
-                );
-                return Err(error::ErrorInternalServerError("remote rpc error"));
+            match chain_state.in_flight.entry(cache_key.clone()) {
+                Entry::Occupied(entry) => followers.push((*id, entry.get().subscribe())),
+                Entry::Vacant(entry) => {
+                    let (tx, _rx) = broadcast::channel(1);
+                    entry.insert(tx);
+                    leader_ids.push(*id);
+                }
             }
+        }
 
-            let result = &response["result"];
-            request_result.insert(id, result.clone());
+        if !leader_ids.is_empty() {
+            let request_body = Value::Array(
+                leader_ids
+                    .iter()
+                    .map(|id| {
+                        let (method, params, _) = uncached_requests.get(id).unwrap();
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "method": method.to_string(),
+                            "params": params.clone(),
+                        })
+                    })
+                    .collect::<Vec<Value>>(),
+            );
+
+            let rpc_result = request_rpc(chain_state.rpc_url.clone(), &request_body).await;
+
+            let rpc_result = match rpc_result {
+                Ok(rpc_result) => rpc_result,
+                Err(err) => {
+                    log::error!("fail to make rpc request because: {}", err);
+                    fail_pending_leaders(chain_state, &leader_ids, &uncached_requests, "remote rpc error");
+                    return Err(error::ErrorInternalServerError(format!(
+                        "fail to make rpc request because: {}",
+                        err
+                    )));
+                }
+            };
 
-            let cache_key = match cache_key {
-                Some(cache_key) => cache_key.clone(),
-                None => continue,
+            let rpc_result = match rpc_result.as_array() {
+                Some(rpc_result) => rpc_result,
+                None => {
+                    log::error!("invalid rpc response: {}", rpc_result);
+                    fail_pending_leaders(chain_state, &leader_ids, &uncached_requests, "invalid rpc response");
+                    return Err(error::ErrorInternalServerError("invalid rpc response"));
+                }
             };
 
-            let cache_entry = chain_state.cache_entries.get(method).unwrap();
+            let mut cache_writes = Vec::new();
+
+            for response in rpc_result {
+                let id = match response["id"].as_u64() {
+                    Some(id) => id,
+                    None => {
+                        log::error!("invalid rpc response: id not found in {}", response);
+                        fail_pending_leaders(
+                            chain_state,
+                            &leader_ids,
+                            &uncached_requests,
+                            "invalid rpc response",
+                        );
+                        return Err(error::ErrorBadRequest("id not found"));
+                    }
+                };
+
+                let Some((method, params, cache_key)) = uncached_requests.get(&id) else {
+                    log::error!("rpc response id {} does not match any pending request", id);
+                    fail_pending_leaders(
+                        chain_state,
+                        &leader_ids,
+                        &uncached_requests,
+                        "invalid rpc response",
+                    );
+                    return Err(error::ErrorBadRequest("unexpected id in rpc response"));
+                };
+
+                let error = &response["error"];
+                if !error.is_null() {
+                    log::error!(
+                        "rpc error: {}, request: {}({}), response: {}",
+                        error,
+                        method,
+                        params,
+                        response
+                    );
+                    fail_pending_leaders(chain_state, &leader_ids, &uncached_requests, "remote rpc error");
+                    return Err(error::ErrorInternalServerError("remote rpc error"));
+                }
+
+                let result = response["result"].clone();
+                request_result.insert(id, result.clone());
+
+                let Some(cache_key) = cache_key else {
+                    continue;
+                };
+
+                let cache_entry = chain_state.cache_entries.get(method).unwrap();
+
+                let (can_cache, extracted_value) = cache_entry
+                    .handler
+                    .extract_cache_value(&result)
+                    .expect("fail to extract cache value");
+
+                if can_cache {
+                    let ttl = cache_entry.handler.cache_ttl(params).or(chain_state.default_ttl);
+                    cache_writes.push((cache_key.clone(), extracted_value, ttl));
+                }
+
+                if let Some((_, sender)) = chain_state.in_flight.remove(cache_key) {
+                    let _ = sender.send(Ok(result));
+                }
+            }
 
-            let (can_cache, extracted_value) = cache_entry
-                .handler
-                .extract_cache_value(result)
-                .expect("fail to extract cache value");
+            // Flush every cacheable value from the batch in a single
+            // pipelined write instead of one round trip per result.
+            if !cache_writes.is_empty() {
+                if let Err(err) = chain_state.cache.set_many(cache_writes).await {
+                    log::error!("fail to batch write cache because: {}", err);
+                }
+            }
 
-            if can_cache {
-                let value = extracted_value.as_str();
-                let _ = REDIS
-                    .get_connection()
-                    .unwrap()
-                    .set::<_, _, String>(cache_key.clone(), value);
+            // A well-formed upstream response carries one element per
+            // leader id; if any are missing (a malformed/partial batch
+            // response), those in_flight entries would otherwise never be
+            // removed, leaving coalesced followers waiting forever.
+            let missing_ids = leader_ids
+                .iter()
+                .copied()
+                .filter(|id| !request_result.contains_key(id))
+                .collect::<Vec<u64>>();
+
+            if !missing_ids.is_empty() {
+                log::error!("invalid rpc response: missing response for ids {:?}", missing_ids);
+                fail_pending_leaders(chain_state, &leader_ids, &uncached_requests, "invalid rpc response");
+                return Err(error::ErrorInternalServerError("invalid rpc response"));
+            }
+        }
+
+        for (id, mut rx) in followers {
+            match rx.recv().await {
+                Ok(Ok(value)) => {
+                    request_result.insert(id, value);
+                }
+                Ok(Err(err)) => {
+                    log::error!("coalesced rpc request failed: {}", err);
+                    return Err(error::ErrorInternalServerError("remote rpc error"));
+                }
+                Err(err) => {
+                    log::error!("fail to receive coalesced rpc result: {}", err);
+                    return Err(error::ErrorInternalServerError(
+                        "fail to receive coalesced rpc result",
+                    ));
+                }
             }
         }
     }
@@ -265,7 +447,12 @@ async fn main() -> std::io::Result<()> {
     for (name, rpc_url) in arg.endpoints.iter() {
         log::info!("Adding endpoint {} linked to {}", name, rpc_url);
 
-        let mut chain_state = ChainState::new(rpc_url.clone());
+        let cache = build_cache(&arg)
+            .await
+            .context("fail to build cache backend")
+            .map_err(std::io::Error::other)?;
+        let default_ttl = arg.default_ttl.map(Duration::from_secs);
+        let mut chain_state = ChainState::new(rpc_url.clone(), cache, default_ttl);
 
         for factory in &handler_factories {
             let handler = factory();
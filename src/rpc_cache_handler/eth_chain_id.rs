@@ -0,0 +1,25 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use super::RpcCacheHandler;
+
+/// `eth_chainId` never changes for a given endpoint, so it's always cacheable.
+struct EthChainId;
+
+impl RpcCacheHandler for EthChainId {
+    fn method_name(&self) -> &'static str {
+        "eth_chainId"
+    }
+
+    fn extract_cache_key(&self, _params: &Value) -> Result<Option<String>> {
+        Ok(Some("eth_chainId".to_string()))
+    }
+
+    fn extract_cache_value(&self, result: &Value) -> Result<(bool, String)> {
+        Ok((true, result.to_string()))
+    }
+}
+
+pub fn factory() -> Box<dyn RpcCacheHandler> {
+    Box::new(EthChainId)
+}
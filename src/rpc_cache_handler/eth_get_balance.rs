@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use super::RpcCacheHandler;
+
+/// A balance at a specific historical block is immutable, but a balance at
+/// `"latest"` moves with every incoming transaction, so it's cached only
+/// briefly to avoid serving stale reads across a reorg.
+const LATEST_BALANCE_TTL: Duration = Duration::from_secs(4);
+
+struct EthGetBalance;
+
+impl RpcCacheHandler for EthGetBalance {
+    fn method_name(&self) -> &'static str {
+        "eth_getBalance"
+    }
+
+    fn extract_cache_key(&self, params: &Value) -> Result<Option<String>> {
+        let address = params
+            .get(0)
+            .and_then(Value::as_str)
+            .context("missing address param")?;
+        let block_tag = params.get(1).and_then(Value::as_str).unwrap_or("latest");
+
+        if block_tag == "pending" {
+            return Ok(None);
+        }
+
+        Ok(Some(format!("eth_getBalance:{}:{}", address, block_tag)))
+    }
+
+    fn extract_cache_value(&self, result: &Value) -> Result<(bool, String)> {
+        Ok((!result.is_null(), result.to_string()))
+    }
+
+    fn cache_ttl(&self, params: &Value) -> Option<Duration> {
+        let block_tag = params.get(1).and_then(Value::as_str).unwrap_or("latest");
+
+        if block_tag == "latest" {
+            Some(LATEST_BALANCE_TTL)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn factory() -> Box<dyn RpcCacheHandler> {
+    Box::new(EthGetBalance)
+}
@@ -0,0 +1,37 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use super::RpcCacheHandler;
+
+/// `"latest"`/`"pending"` block tags are never cacheable since they move with
+/// every new block; a specific block number is immutable once mined.
+struct EthGetBlockByNumber;
+
+impl RpcCacheHandler for EthGetBlockByNumber {
+    fn method_name(&self) -> &'static str {
+        "eth_getBlockByNumber"
+    }
+
+    fn extract_cache_key(&self, params: &Value) -> Result<Option<String>> {
+        let block_tag = params.get(0).and_then(Value::as_str).unwrap_or("latest");
+
+        if matches!(block_tag, "latest" | "pending") {
+            return Ok(None);
+        }
+
+        let full_tx = params.get(1).and_then(Value::as_bool).unwrap_or(false);
+
+        Ok(Some(format!(
+            "eth_getBlockByNumber:{}:{}",
+            block_tag, full_tx
+        )))
+    }
+
+    fn extract_cache_value(&self, result: &Value) -> Result<(bool, String)> {
+        Ok((!result.is_null(), result.to_string()))
+    }
+}
+
+pub fn factory() -> Box<dyn RpcCacheHandler> {
+    Box::new(EthGetBlockByNumber)
+}
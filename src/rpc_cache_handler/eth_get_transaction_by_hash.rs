@@ -0,0 +1,37 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use super::RpcCacheHandler;
+
+/// A mined transaction is immutable once it has a `blockNumber`, so only
+/// those responses are cached; pending transactions are not.
+struct EthGetTransactionByHash;
+
+impl RpcCacheHandler for EthGetTransactionByHash {
+    fn method_name(&self) -> &'static str {
+        "eth_getTransactionByHash"
+    }
+
+    fn extract_cache_key(&self, params: &Value) -> Result<Option<String>> {
+        let tx_hash = params
+            .get(0)
+            .and_then(Value::as_str)
+            .context("missing transaction hash param")?;
+
+        Ok(Some(format!("eth_getTransactionByHash:{}", tx_hash)))
+    }
+
+    fn extract_cache_value(&self, result: &Value) -> Result<(bool, String)> {
+        if result.is_null() {
+            return Ok((false, String::new()));
+        }
+
+        let can_cache = result.get("blockNumber").is_some_and(|v| !v.is_null());
+
+        Ok((can_cache, result.to_string()))
+    }
+}
+
+pub fn factory() -> Box<dyn RpcCacheHandler> {
+    Box::new(EthGetTransactionByHash)
+}
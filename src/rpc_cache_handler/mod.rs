@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde_json::Value;
+
+mod eth_chain_id;
+mod eth_get_balance;
+mod eth_get_block_by_number;
+mod eth_get_transaction_by_hash;
+
+/// Caches the response for a single JSON-RPC method.
+pub trait RpcCacheHandler: Send + Sync {
+    /// The JSON-RPC method name this handler caches.
+    fn method_name(&self) -> &'static str;
+
+    /// Derive the cache key for a request's `params`, or `None` if this
+    /// particular request can't be served from cache.
+    fn extract_cache_key(&self, params: &Value) -> Result<Option<String>>;
+
+    /// Decide whether `result` is cacheable and, if so, its serialized value.
+    fn extract_cache_value(&self, result: &Value) -> Result<(bool, String)>;
+
+    /// How long a cached value should live before it expires. Defaults to
+    /// `None`, meaning the handler sets no TTL of its own: the value then
+    /// gets the global `--default-ttl` if one is set and otherwise never
+    /// expires, which is only safe for keys that are immutable once written
+    /// (e.g. a specific mined block).
+    /// Handlers that cache block-tag-relative or reorg-sensitive results
+    /// should override this to return a short TTL instead.
+    fn cache_ttl(&self, _params: &Value) -> Option<Duration> {
+        None
+    }
+}
+
+type HandlerFactory = fn() -> Box<dyn RpcCacheHandler>;
+
+pub fn all_factories() -> Vec<HandlerFactory> {
+    vec![
+        eth_chain_id::factory,
+        eth_get_balance::factory,
+        eth_get_block_by_number::factory,
+        eth_get_transaction_by_hash::factory,
+    ]
+}